@@ -13,17 +13,53 @@ pub enum JsonValue {
   Null,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Pos {
+  pub offset: usize,
+  pub line: usize,
+  pub col: usize,
+}
+
 struct Lex<'json> {
   code: Peekable<Chars<'json>>,
+  offset: usize,
+  line: usize,
+  col: usize,
+  last_pos: Pos,
 }
 
 impl<'json> Lex<'json> {
   fn new(code: &'json str) -> Self {
     let code = code.chars().peekable();
-    Self { code }
+    Self { code, offset: 0, line: 1, col: 1, last_pos: Pos::default() }
+  }
+
+  fn pos(&self) -> Pos {
+    Pos { offset: self.offset, line: self.line, col: self.col }
+  }
+
+  fn bump(&mut self) -> Option<char> {
+    let c = self.code.next();
+    if let Some(c) = c {
+      self.offset += c.len_utf8();
+      if c == '\n' {
+        self.line += 1;
+        self.col = 1;
+      } else {
+        self.col += 1;
+      }
+    }
+    c
   }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IllegalKind {
+  Number,
+  String,
+  Identifier,
+}
+
 #[derive(Debug)]
 enum Token {
   Str(String),
@@ -38,48 +74,186 @@ enum Token {
   Comma,
   Colon,
   Eof,
-  IllegalIdent,
+  Illegal(IllegalKind),
+}
+
+fn describe_token(tok: &Token) -> String {
+  match tok {
+    Token::Str(s) => format!("string {s:?}"),
+    Token::Num(n) => format!("number {n}"),
+    Token::False => "'false'".to_string(),
+    Token::True => "'true'".to_string(),
+    Token::Null => "'null'".to_string(),
+    Token::LBrace => "'{'".to_string(),
+    Token::RBrace => "'}'".to_string(),
+    Token::LBracket => "'['".to_string(),
+    Token::RBracket => "']'".to_string(),
+    Token::Comma => "','".to_string(),
+    Token::Colon => "':'".to_string(),
+    Token::Eof => "end of input".to_string(),
+    Token::Illegal(IllegalKind::Number) => "an invalid number".to_string(),
+    Token::Illegal(IllegalKind::String) => "an invalid string".to_string(),
+    Token::Illegal(IllegalKind::Identifier) => "an invalid identifier".to_string(),
+  }
 }
 
 impl<'json> Lex<'json> {
   fn next_token(&mut self) -> Token {
-    if let Some(chr) = self.code.peek() {
+    if let Some(&chr) = self.code.peek() {
       match chr {
         ' ' | '\n' | '\t' | '\r' => {
-          self.code.next();
+          self.bump();
           self.next_token()
         }
-        '"' => self.str(),
-        ':' => self.just(Token::Colon),
-        ',' => self.just(Token::Comma),
-        '[' => self.just(Token::LBracket),
-        ']' => self.just(Token::RBracket),
-        '{' => self.just(Token::LBrace),
-        '}' => self.just(Token::RBrace),
-        n if n.is_ascii_digit() => self.num(),
-        _ => self.ident(),
+        _ => {
+          self.last_pos = self.pos();
+          match chr {
+            '"' => self.str(),
+            ':' => self.just(Token::Colon),
+            ',' => self.just(Token::Comma),
+            '[' => self.just(Token::LBracket),
+            ']' => self.just(Token::RBracket),
+            '{' => self.just(Token::LBrace),
+            '}' => self.just(Token::RBrace),
+            n if n.is_ascii_digit() => self.num(),
+            '-' => self.num(),
+            _ => self.ident(),
+          }
+        }
       }
     } else {
+      self.last_pos = self.pos();
       Token::Eof
     }
   }
 
   fn str(&mut self) -> Token {
-    self.code.next();
-    let s = self
-      .code
-      .by_ref()
-      .take_while(|c| *c != '"')
-      .collect::<String>();
-    Token::Str(s)
+    self.bump();
+    let mut s = String::new();
+
+    loop {
+      match self.bump() {
+        Some('"') => return Token::Str(s),
+        Some('\\') => match self.bump() {
+          Some('"') => s.push('"'),
+          Some('\\') => s.push('\\'),
+          Some('/') => s.push('/'),
+          Some('b') => s.push('\u{8}'),
+          Some('f') => s.push('\u{c}'),
+          Some('n') => s.push('\n'),
+          Some('r') => s.push('\r'),
+          Some('t') => s.push('\t'),
+          Some('u') => {
+            let hi = match self.hex4() {
+              Some(n) => n,
+              None => return Token::Illegal(IllegalKind::String),
+            };
+            let code_point = if (0xD800..=0xDBFF).contains(&hi) {
+              if self.bump() != Some('\\') || self.bump() != Some('u') {
+                return Token::Illegal(IllegalKind::String);
+              }
+              let lo = match self.hex4() {
+                Some(n) => n,
+                None => return Token::Illegal(IllegalKind::String),
+              };
+              if !(0xDC00..=0xDFFF).contains(&lo) {
+                return Token::Illegal(IllegalKind::String);
+              }
+              0x10000 + ((hi - 0xD800) << 10) + (lo - 0xDC00)
+            } else {
+              hi
+            };
+            match char::from_u32(code_point) {
+              Some(c) => s.push(c),
+              None => return Token::Illegal(IllegalKind::String),
+            }
+          }
+          _ => return Token::Illegal(IllegalKind::String),
+        },
+        Some(c) => s.push(c),
+        None => return Token::Illegal(IllegalKind::String),
+      }
+    }
+  }
+
+  fn hex4(&mut self) -> Option<u32> {
+    let mut n = 0u32;
+    for _ in 0..4 {
+      let digit = self.bump()?.to_digit(16)?;
+      n = n * 16 + digit;
+    }
+    Some(n)
   }
 
   fn num(&mut self) -> Token {
-    todo!()
+    let mut s = String::new();
+
+    if let Some('-') = self.code.peek() {
+      s.push(self.bump().unwrap());
+    }
+
+    match self.code.peek() {
+      Some('0') => {
+        s.push(self.bump().unwrap());
+        if matches!(self.code.peek(), Some(c) if c.is_ascii_digit()) {
+          return Token::Illegal(IllegalKind::Number);
+        }
+      }
+      Some(c) if c.is_ascii_digit() => {
+        while let Some(c) = self.code.peek() {
+          if c.is_ascii_digit() {
+            s.push(self.bump().unwrap());
+          } else {
+            break;
+          }
+        }
+      }
+      _ => return Token::Illegal(IllegalKind::Number),
+    }
+
+    if let Some('.') = self.code.peek() {
+      s.push(self.bump().unwrap());
+      match self.code.peek() {
+        Some(c) if c.is_ascii_digit() => {
+          while let Some(c) = self.code.peek() {
+            if c.is_ascii_digit() {
+              s.push(self.bump().unwrap());
+            } else {
+              break;
+            }
+          }
+        }
+        _ => return Token::Illegal(IllegalKind::Number),
+      }
+    }
+
+    if let Some('e' | 'E') = self.code.peek() {
+      s.push(self.bump().unwrap());
+      if let Some('+' | '-') = self.code.peek() {
+        s.push(self.bump().unwrap());
+      }
+      match self.code.peek() {
+        Some(c) if c.is_ascii_digit() => {
+          while let Some(c) = self.code.peek() {
+            if c.is_ascii_digit() {
+              s.push(self.bump().unwrap());
+            } else {
+              break;
+            }
+          }
+        }
+        _ => return Token::Illegal(IllegalKind::Number),
+      }
+    }
+
+    match s.parse::<f64>() {
+      Ok(n) => Token::Num(n),
+      Err(_) => Token::Illegal(IllegalKind::Number),
+    }
   }
 
   fn just(&mut self, t: Token) -> Token {
-    self.code.next();
+    self.bump();
     t
   }
 
@@ -87,7 +261,7 @@ impl<'json> Lex<'json> {
     let mut s = String::new();
     while let Some(chr) = self.code.peek() {
       if chr.is_alphanumeric() {
-        s.push(self.code.next().unwrap());
+        s.push(self.bump().unwrap());
       } else {
         break;
       }
@@ -99,14 +273,51 @@ impl<'json> Lex<'json> {
     } else if &s == "null" {
       Token::Null
     } else {
-      Token::IllegalIdent
+      Token::Illegal(IllegalKind::Identifier)
     }
   }
 }
 
+#[derive(Debug)]
+pub enum ParseError {
+  UnexpectedToken { expected: String, found: String, pos: Pos },
+  UnexpectedEof { pos: Pos },
+  TrailingData { pos: Pos },
+  InvalidNumber { pos: Pos },
+  InvalidString { pos: Pos },
+}
+
+impl std::fmt::Display for ParseError {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ParseError::UnexpectedToken { expected, found, pos } => write!(
+        f,
+        "parse error at {}:{}: expected {expected}, found {found}",
+        pos.line, pos.col
+      ),
+      ParseError::UnexpectedEof { pos } => {
+        write!(f, "parse error at {}:{}: unexpected end of input", pos.line, pos.col)
+      }
+      ParseError::TrailingData { pos } => {
+        write!(f, "parse error at {}:{}: trailing data after value", pos.line, pos.col)
+      }
+      ParseError::InvalidNumber { pos } => {
+        write!(f, "parse error at {}:{}: invalid number literal", pos.line, pos.col)
+      }
+      ParseError::InvalidString { pos } => {
+        write!(f, "parse error at {}:{}: invalid string literal", pos.line, pos.col)
+      }
+    }
+  }
+}
+
+impl std::error::Error for ParseError {}
+
 pub struct Par<'json> {
   cur: Token,
+  cur_pos: Pos,
   nxt: Token,
+  nxt_pos: Pos,
   lex: Lex<'json>,
   mem: Allocator<JsonValue>,
 }
@@ -114,28 +325,36 @@ pub struct Par<'json> {
 impl<'json> Par<'json> {
   fn init(mut lex: Lex<'json>, mem: usize) -> Self {
     let cur = lex.next_token();
+    let cur_pos = lex.last_pos;
     let nxt = lex.next_token();
+    let nxt_pos = lex.last_pos;
     let mem = Allocator::make(mem);
-    Self { cur, nxt, lex, mem }
+    Self { cur, cur_pos, nxt, nxt_pos, lex, mem }
   }
 
   fn advance(&mut self) -> Token {
     let mut ret = self.lex.next_token();
+    let mut ret_pos = self.lex.last_pos;
     std::mem::swap(&mut self.nxt, &mut self.cur);
     std::mem::swap(&mut self.nxt, &mut ret);
+    std::mem::swap(&mut self.nxt_pos, &mut self.cur_pos);
+    std::mem::swap(&mut self.nxt_pos, &mut ret_pos);
     ret
   }
 
   pub fn parse(
     src: &'json str,
     mem: usize,
-  ) -> Result<(JsonValue, Allocator<JsonValue>), String> {
+  ) -> Result<(JsonValue, Allocator<JsonValue>), ParseError> {
     let mut parser = Self::init(Lex::new(src), mem);
     let result = parser.go_parse()?;
+    if !matches!(parser.cur, Token::Eof) {
+      return Err(ParseError::TrailingData { pos: parser.cur_pos });
+    }
     Ok((result, parser.mem))
   }
 
-  pub fn go_parse(&mut self) -> Result<JsonValue, String> {
+  pub fn go_parse(&mut self) -> Result<JsonValue, ParseError> {
     let tk = match &mut self.cur {
       Token::False => Ok(JsonValue::Bool(false)),
       Token::True => Ok(JsonValue::Bool(true)),
@@ -159,7 +378,11 @@ impl<'json> Par<'json> {
         }
         Ok(JsonValue::List(list))
       }
-      Token::RBracket => todo!(),
+      Token::RBracket => Err(ParseError::UnexpectedToken {
+        expected: "a value".to_string(),
+        found: describe_token(&self.cur),
+        pos: self.cur_pos,
+      }),
 
       Token::LBrace => {
         let mut obj = HashMap::new();
@@ -175,7 +398,11 @@ impl<'json> Par<'json> {
           if matches!(self.cur, Token::Colon) {
             self.advance();
           } else {
-            return Err("Expected ':'.".to_string());
+            return Err(ParseError::UnexpectedToken {
+              expected: "':'".to_string(),
+              found: describe_token(&self.cur),
+              pos: self.cur_pos,
+            });
           }
           let val = self.go_parse()?;
           let id = self.mem.alloc(val);
@@ -183,25 +410,341 @@ impl<'json> Par<'json> {
         }
         Ok(JsonValue::Object(obj))
       }
-      Token::RBrace => todo!(),
+      Token::RBrace => Err(ParseError::UnexpectedToken {
+        expected: "a value".to_string(),
+        found: describe_token(&self.cur),
+        pos: self.cur_pos,
+      }),
 
-      Token::Comma => todo!(),
-      Token::Colon => todo!(),
+      Token::Comma => Err(ParseError::UnexpectedToken {
+        expected: "a value".to_string(),
+        found: describe_token(&self.cur),
+        pos: self.cur_pos,
+      }),
+      Token::Colon => Err(ParseError::UnexpectedToken {
+        expected: "a value".to_string(),
+        found: describe_token(&self.cur),
+        pos: self.cur_pos,
+      }),
 
-      Token::Eof => return Err("Reached EOF.".to_string()),
-      Token::IllegalIdent => todo!(),
+      Token::Eof => return Err(ParseError::UnexpectedEof { pos: self.cur_pos }),
+      Token::Illegal(IllegalKind::Number) => {
+        return Err(ParseError::InvalidNumber { pos: self.cur_pos })
+      }
+      Token::Illegal(IllegalKind::String) => {
+        return Err(ParseError::InvalidString { pos: self.cur_pos })
+      }
+      Token::Illegal(IllegalKind::Identifier) => Err(ParseError::UnexpectedToken {
+        expected: "a value".to_string(),
+        found: describe_token(&self.cur),
+        pos: self.cur_pos,
+      }),
     };
     self.advance();
     tk
   }
 
-  fn expect_str(&mut self) -> Result<String, String> {
-    let s = match &mut self.cur {
-      Token::Str(s) => std::mem::take(s),
-      _ => return Err("Key is not a String".to_string()),
+  fn expect_str(&mut self) -> Result<String, ParseError> {
+    match std::mem::replace(&mut self.cur, Token::Eof) {
+      Token::Str(s) => {
+        self.advance();
+        Ok(s)
+      }
+      other => {
+        let found = describe_token(&other);
+        Err(ParseError::UnexpectedToken {
+          expected: "a string key".to_string(),
+          found,
+          pos: self.cur_pos,
+        })
+      }
+    }
+  }
+}
+
+pub fn to_string(value: &JsonValue, mem: &Allocator<JsonValue>) -> String {
+  let mut out = String::new();
+  encode_value(value, mem, &mut out);
+  out
+}
+
+pub fn to_string_pretty(
+  value: &JsonValue,
+  mem: &Allocator<JsonValue>,
+  indent: usize,
+) -> String {
+  let mut out = String::new();
+  encode_value_pretty(value, mem, indent, 0, &mut out);
+  out
+}
+
+fn encode_value(value: &JsonValue, mem: &Allocator<JsonValue>, out: &mut String) {
+  match value {
+    JsonValue::Null => out.push_str("null"),
+    JsonValue::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+    JsonValue::Number(n) => out.push_str(&encode_num(*n)),
+    JsonValue::String(s) => encode_str(s, out),
+    JsonValue::List(list) => {
+      out.push('[');
+      for (i, id) in list.iter().enumerate() {
+        if i > 0 {
+          out.push(',');
+        }
+        encode_value(mem.fetch(id), mem, out);
+      }
+      out.push(']');
+    }
+    JsonValue::Object(obj) => {
+      out.push('{');
+      let mut members: Vec<_> = obj.iter().collect();
+      members.sort_by(|a, b| a.0.cmp(b.0));
+      for (i, (key, id)) in members.into_iter().enumerate() {
+        if i > 0 {
+          out.push(',');
+        }
+        encode_str(key, out);
+        out.push(':');
+        encode_value(mem.fetch(id), mem, out);
+      }
+      out.push('}');
+    }
+  }
+}
+
+fn encode_value_pretty(
+  value: &JsonValue,
+  mem: &Allocator<JsonValue>,
+  indent: usize,
+  depth: usize,
+  out: &mut String,
+) {
+  match value {
+    JsonValue::List(list) if !list.is_empty() => {
+      out.push('[');
+      for (i, id) in list.iter().enumerate() {
+        if i > 0 {
+          out.push(',');
+        }
+        out.push('\n');
+        out.push_str(&" ".repeat(indent * (depth + 1)));
+        encode_value_pretty(mem.fetch(id), mem, indent, depth + 1, out);
+      }
+      out.push('\n');
+      out.push_str(&" ".repeat(indent * depth));
+      out.push(']');
+    }
+    JsonValue::Object(obj) if !obj.is_empty() => {
+      out.push('{');
+      for (i, (key, id)) in obj.iter().enumerate() {
+        if i > 0 {
+          out.push(',');
+        }
+        out.push('\n');
+        out.push_str(&" ".repeat(indent * (depth + 1)));
+        encode_str(key, out);
+        out.push_str(": ");
+        encode_value_pretty(mem.fetch(id), mem, indent, depth + 1, out);
+      }
+      out.push('\n');
+      out.push_str(&" ".repeat(indent * depth));
+      out.push('}');
+    }
+    JsonValue::List(_) => out.push_str("[]"),
+    JsonValue::Object(_) => out.push_str("{}"),
+    other => encode_value(other, mem, out),
+  }
+}
+
+fn encode_num(n: f64) -> String {
+  if !n.is_finite() {
+    // NaN/Infinity have no JSON representation; encode them the way most
+    // JSON encoders do rather than emitting text no JSON reader can parse.
+    "null".to_string()
+  } else if n.fract() == 0.0 && n.abs() < 1e15 {
+    format!("{}", n as i64)
+  } else {
+    format!("{n}")
+  }
+}
+
+fn encode_str(s: &str, out: &mut String) {
+  out.push('"');
+  for c in s.chars() {
+    match c {
+      '"' => out.push_str("\\\""),
+      '\\' => out.push_str("\\\\"),
+      '\u{8}' => out.push_str("\\b"),
+      '\u{c}' => out.push_str("\\f"),
+      '\n' => out.push_str("\\n"),
+      '\r' => out.push_str("\\r"),
+      '\t' => out.push_str("\\t"),
+      c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+      c => out.push(c),
+    }
+  }
+  out.push('"');
+}
+
+pub fn pointer<'a>(
+  root: &'a JsonValue,
+  mem: &'a Allocator<JsonValue>,
+  path: &str,
+) -> Option<&'a JsonValue> {
+  if path.is_empty() {
+    return Some(root);
+  }
+
+  let mut current = root;
+  for raw_token in path.split('/').skip(1) {
+    let token = raw_token.replace("~1", "/").replace("~0", "~");
+    current = match current {
+      JsonValue::Object(obj) => mem.fetch(obj.get(&token)?),
+      JsonValue::List(list) => {
+        if token != "0" && token.starts_with('0') {
+          return None;
+        }
+        mem.fetch(list.get(token.parse::<usize>().ok()?)?)
+      }
+      _ => return None,
     };
-    self.advance();
-    Ok(s)
+  }
+  Some(current)
+}
+
+#[derive(Debug, PartialEq)]
+pub enum JsonEvent {
+  BeginObject,
+  ObjectKey(String),
+  EndObject,
+  BeginArray,
+  EndArray,
+  StringValue(String),
+  NumberValue(f64),
+  BoolValue(bool),
+  NullValue,
+}
+
+#[derive(Clone, Copy)]
+enum Frame {
+  Array { started: bool },
+  Object { started: bool, awaiting_value: bool },
+}
+
+pub struct StreamingParser<'json> {
+  lex: Lex<'json>,
+  cur: Token,
+  stack: Vec<Frame>,
+  done: bool,
+}
+
+impl<'json> StreamingParser<'json> {
+  pub fn new(src: &'json str) -> Self {
+    let mut lex = Lex::new(src);
+    let cur = lex.next_token();
+    Self { lex, cur, stack: Vec::new(), done: false }
+  }
+
+  fn advance(&mut self) -> Token {
+    std::mem::replace(&mut self.cur, self.lex.next_token())
+  }
+
+  fn parse_value_event(&mut self) -> Result<JsonEvent, String> {
+    match self.advance() {
+      Token::Str(s) => Ok(JsonEvent::StringValue(s)),
+      Token::Num(n) => Ok(JsonEvent::NumberValue(n)),
+      Token::True => Ok(JsonEvent::BoolValue(true)),
+      Token::False => Ok(JsonEvent::BoolValue(false)),
+      Token::Null => Ok(JsonEvent::NullValue),
+      Token::LBracket => {
+        self.stack.push(Frame::Array { started: false });
+        Ok(JsonEvent::BeginArray)
+      }
+      Token::LBrace => {
+        self.stack.push(Frame::Object { started: false, awaiting_value: false });
+        Ok(JsonEvent::BeginObject)
+      }
+      Token::Eof => Err("Reached EOF.".to_string()),
+      _ => Err("Expected a value.".to_string()),
+    }
+  }
+}
+
+impl<'json> Iterator for StreamingParser<'json> {
+  type Item = Result<JsonEvent, String>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.done {
+      return None;
+    }
+
+    let top = self.stack.last().copied();
+
+    let result = match top {
+      None => {
+        let ev = self.parse_value_event();
+        if self.stack.is_empty() {
+          self.done = true;
+        }
+        ev
+      }
+      Some(Frame::Array { started }) => {
+        if matches!(self.cur, Token::RBracket) {
+          self.advance();
+          self.stack.pop();
+          Ok(JsonEvent::EndArray)
+        } else if started && !matches!(self.cur, Token::Comma) {
+          Err("Expected ',' or ']'.".to_string())
+        } else {
+          if started {
+            self.advance();
+          }
+          if let Some(Frame::Array { started }) = self.stack.last_mut() {
+            *started = true;
+          }
+          self.parse_value_event()
+        }
+      }
+      Some(Frame::Object { started, awaiting_value }) => {
+        if awaiting_value {
+          if let Some(Frame::Object { awaiting_value, .. }) = self.stack.last_mut() {
+            *awaiting_value = false;
+          }
+          self.parse_value_event()
+        } else if matches!(self.cur, Token::RBrace) {
+          self.advance();
+          self.stack.pop();
+          Ok(JsonEvent::EndObject)
+        } else if started && !matches!(self.cur, Token::Comma) {
+          Err("Expected ',' or '}'.".to_string())
+        } else {
+          if started {
+            self.advance();
+          }
+          match self.advance() {
+            Token::Str(key) => {
+              if !matches!(self.cur, Token::Colon) {
+                Err("Expected ':'.".to_string())
+              } else {
+                self.advance();
+                if let Some(Frame::Object { started, awaiting_value }) = self.stack.last_mut() {
+                  *started = true;
+                  *awaiting_value = true;
+                }
+                Ok(JsonEvent::ObjectKey(key))
+              }
+            }
+            _ => Err("Expected a string key.".to_string()),
+          }
+        }
+      }
+    };
+
+    if result.is_err() || (self.stack.is_empty() && matches!(result, Ok(JsonEvent::EndObject | JsonEvent::EndArray)))
+    {
+      self.done = true;
+    }
+
+    Some(result)
   }
 }
 
@@ -220,40 +763,262 @@ fn main() {
 }
 
 pub struct Allocator<T> {
-  curr: usize,
-  size: usize,
-  vec: Vec<T>,
+  vec: Vec<Option<T>>,
+  generations: Vec<u32>,
+  free: Vec<usize>,
 }
 
-#[derive(Debug)]
-pub struct Id<T>(usize, PhantomData<T>);
+#[derive(Debug, Clone, Copy)]
+pub struct Id<T>(usize, u32, PhantomData<T>);
 
 impl<T> Id<T> {
   pub fn id(id: usize) -> Self {
-    Self(id, PhantomData)
+    Self(id, 0, PhantomData)
   }
 }
 
 impl<T> Allocator<T> {
   pub fn make(size: usize) -> Self {
     assert!(size > 0);
-    let vec = Vec::with_capacity(size - 1);
     Self {
-      curr: 0,
-      size: size - 1,
-      vec,
+      vec: Vec::with_capacity(size),
+      generations: Vec::with_capacity(size),
+      free: Vec::new(),
     }
   }
 
   pub fn alloc(&mut self, el: T) -> Id<T> {
-    let id = self.curr;
-    assert!(id < self.size);
-    self.vec.push(el);
-    self.curr += 1;
-    Id(id, PhantomData)
+    if let Some(id) = self.free.pop() {
+      self.vec[id] = Some(el);
+      Id(id, self.generations[id], PhantomData)
+    } else {
+      self.vec.push(Some(el));
+      self.generations.push(0);
+      Id(self.vec.len() - 1, 0, PhantomData)
+    }
+  }
+
+  pub fn free(&mut self, Id(id, generation, ..): Id<T>) {
+    if self.generations[id] != generation {
+      return;
+    }
+    self.vec[id] = None;
+    self.generations[id] = self.generations[id].wrapping_add(1);
+    self.free.push(id);
+  }
+
+  pub fn fetch(&self, Id(id, generation, ..): &Id<T>) -> &T {
+    debug_assert_eq!(
+      self.generations[*id], *generation,
+      "stale Id: slot was freed and reused"
+    );
+    self.vec[*id].as_ref().expect("fetched a freed slot")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse_ok(src: &str) -> JsonValue {
+    Par::parse(src, 16).unwrap().0
+  }
+
+  #[test]
+  fn num_valid_forms() {
+    assert!(matches!(parse_ok("0"), JsonValue::Number(n) if n == 0.0));
+    assert!(matches!(parse_ok("-0"), JsonValue::Number(n) if n == 0.0));
+    assert!(matches!(parse_ok("123"), JsonValue::Number(n) if n == 123.0));
+    assert!(matches!(parse_ok("-123.45"), JsonValue::Number(n) if n == -123.45));
+    assert!(matches!(parse_ok("1e10"), JsonValue::Number(n) if n == 1e10));
+  }
+
+  #[test]
+  fn num_rejects_lone_minus() {
+    assert!(matches!(Par::parse("-", 16), Err(ParseError::InvalidNumber { .. })));
+  }
+
+  #[test]
+  fn num_rejects_trailing_dot() {
+    assert!(matches!(Par::parse("1.", 16), Err(ParseError::InvalidNumber { .. })));
+  }
+
+  #[test]
+  fn num_rejects_trailing_exponent() {
+    assert!(matches!(Par::parse("1e", 16), Err(ParseError::InvalidNumber { .. })));
+  }
+
+  #[test]
+  fn num_rejects_leading_zero() {
+    assert!(matches!(Par::parse("01", 16), Err(ParseError::InvalidNumber { .. })));
+    assert!(matches!(Par::parse("[01]", 16), Err(ParseError::InvalidNumber { .. })));
+  }
+
+  #[test]
+  fn string_escaped_quote_and_backslash() {
+    let v = parse_ok(r#""a\"b\\c""#);
+    assert!(matches!(v, JsonValue::String(ref s) if s == "a\"b\\c"));
+  }
+
+  #[test]
+  fn string_escaped_control_chars() {
+    let v = parse_ok(r#""a\nb\tc\rd""#);
+    assert!(matches!(v, JsonValue::String(ref s) if s == "a\nb\tc\rd"));
+  }
+
+  #[test]
+  fn string_unicode_escape() {
+    let v = parse_ok("\"\\u0041\"");
+    assert!(matches!(v, JsonValue::String(ref s) if s == "A"));
+  }
+
+  #[test]
+  fn string_surrogate_pair() {
+    let v = parse_ok("\"\\ud83d\\ude00\"");
+    assert!(matches!(v, JsonValue::String(ref s) if s == "\u{1f600}"));
+  }
+
+  #[test]
+  fn string_rejects_unpaired_surrogate() {
+    assert!(matches!(Par::parse(r#""\ud83d""#, 16), Err(ParseError::InvalidString { .. })));
+  }
+
+  #[test]
+  fn string_rejects_unterminated() {
+    assert!(matches!(Par::parse("\"abc", 16), Err(ParseError::InvalidString { .. })));
+  }
+
+  #[test]
+  fn to_string_round_trips_object_and_array() {
+    let (v, mem) = Par::parse(r#"{"a":1,"b":[true,false,null,"x"]}"#, 16).unwrap();
+    let s = to_string(&v, &mem);
+    let (v2, mem2) = Par::parse(&s, 16).unwrap();
+    assert_eq!(to_string(&v2, &mem2), s);
+  }
+
+  #[test]
+  fn to_string_escapes_special_chars() {
+    let (v, mem) = Par::parse("\"a\\\"b\\nc\"", 16).unwrap();
+    assert_eq!(to_string(&v, &mem), "\"a\\\"b\\nc\"");
+  }
+
+  #[test]
+  fn to_string_pretty_indents_nested_values() {
+    let (v, mem) = Par::parse(r#"{"a":[1,2]}"#, 16).unwrap();
+    let pretty = to_string_pretty(&v, &mem, 2);
+    assert_eq!(pretty, "{\n  \"a\": [\n    1,\n    2\n  ]\n}");
+  }
+
+  #[test]
+  fn encode_num_non_finite_is_null() {
+    assert_eq!(encode_num(f64::INFINITY), "null");
+    assert_eq!(encode_num(f64::NEG_INFINITY), "null");
+    assert_eq!(encode_num(f64::NAN), "null");
+  }
+
+  #[test]
+  fn streaming_parser_emits_events_for_array() {
+    let events: Vec<_> =
+      StreamingParser::new("[1,2]").collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(
+      events,
+      vec![
+        JsonEvent::BeginArray,
+        JsonEvent::NumberValue(1.0),
+        JsonEvent::NumberValue(2.0),
+        JsonEvent::EndArray,
+      ]
+    );
+  }
+
+  #[test]
+  fn streaming_parser_emits_events_for_object() {
+    let events: Vec<_> =
+      StreamingParser::new(r#"{"a":1}"#).collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(
+      events,
+      vec![
+        JsonEvent::BeginObject,
+        JsonEvent::ObjectKey("a".to_string()),
+        JsonEvent::NumberValue(1.0),
+        JsonEvent::EndObject,
+      ]
+    );
+  }
+
+  #[test]
+  fn streaming_parser_rejects_missing_comma_in_array() {
+    let mut iter = StreamingParser::new("[1 2]");
+    assert_eq!(iter.next(), Some(Ok(JsonEvent::BeginArray)));
+    assert_eq!(iter.next(), Some(Ok(JsonEvent::NumberValue(1.0))));
+    assert!(matches!(iter.next(), Some(Err(_))));
+  }
+
+  #[test]
+  fn streaming_parser_rejects_missing_comma_in_object() {
+    let mut iter = StreamingParser::new(r#"{"a":1 "b":2}"#);
+    assert_eq!(iter.next(), Some(Ok(JsonEvent::BeginObject)));
+    assert_eq!(iter.next(), Some(Ok(JsonEvent::ObjectKey("a".to_string()))));
+    assert_eq!(iter.next(), Some(Ok(JsonEvent::NumberValue(1.0))));
+    assert!(matches!(iter.next(), Some(Err(_))));
+  }
+
+  #[test]
+  fn pointer_resolves_nested_path() {
+    let (root, mem) = Par::parse(r#"{"a":{"b":[1,2,3]}}"#, 16).unwrap();
+    let found = pointer(&root, &mem, "/a/b/1").unwrap();
+    assert!(matches!(found, JsonValue::Number(n) if *n == 2.0));
+  }
+
+  #[test]
+  fn pointer_unescapes_tilde_and_slash() {
+    let (root, mem) = Par::parse(r#"{"a/b":{"c~d":1}}"#, 16).unwrap();
+    let found = pointer(&root, &mem, "/a~1b/c~0d").unwrap();
+    assert!(matches!(found, JsonValue::Number(n) if *n == 1.0));
+  }
+
+  #[test]
+  fn pointer_rejects_leading_zero_index() {
+    let (root, mem) = Par::parse("[1,2,3]", 16).unwrap();
+    assert!(pointer(&root, &mem, "/01").is_none());
+    assert!(pointer(&root, &mem, "/0").is_some());
+  }
+
+  #[test]
+  fn pointer_missing_key_returns_none() {
+    let (root, mem) = Par::parse(r#"{"a":1}"#, 16).unwrap();
+    assert!(pointer(&root, &mem, "/b").is_none());
+  }
+
+  #[test]
+  fn allocator_reuses_freed_slots() {
+    let mut mem = Allocator::make(4);
+    let a = mem.alloc("a");
+    let b = mem.alloc("b");
+    mem.free(a);
+    let c = mem.alloc("c");
+    assert_eq!(c.0, a.0);
+    assert_eq!(*mem.fetch(&c), "c");
+    assert_eq!(*mem.fetch(&b), "b");
+  }
+
+  #[test]
+  fn allocator_grows_past_initial_hint() {
+    let mut mem = Allocator::make(1);
+    let ids: Vec<_> = (0..8).map(|i| mem.alloc(i)).collect();
+    for (i, id) in ids.iter().enumerate() {
+      assert_eq!(*mem.fetch(id), i);
+    }
   }
 
-  pub fn fetch(&self, Id(id, ..): Id<T>) -> &T {
-    &self.vec[id]
+  #[test]
+  #[should_panic(expected = "stale Id")]
+  fn allocator_fetch_panics_on_stale_generation() {
+    let mut mem = Allocator::make(4);
+    let a = mem.alloc("a");
+    let stale = a;
+    mem.free(a);
+    mem.alloc("b");
+    mem.fetch(&stale);
   }
 }